@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A progress update for one in-flight download, pushed back to the UI via `evaluate_script`.
+#[derive(Debug, Clone)]
+pub struct DownloadEvent {
+    pub id: u32,
+    pub received: u64,
+    pub total: Option<u64>,
+    pub state: &'static str, // "started" | "downloading" | "paused" | "done" | "cancelled" | "error"
+}
+
+impl DownloadEvent {
+    /// Renders this event as a call into the UI's download callback.
+    pub fn to_js(&self) -> String {
+        let total = self
+            .total
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| "null".to_string());
+        format!(
+            "window.__CB_DOWNLOAD__ && window.__CB_DOWNLOAD__({{id:{},received:{},total:{},state:\"{}\"}});",
+            self.id, self.received, total, self.state
+        )
+    }
+}
+
+struct DownloadControl {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+/// Tracks in-flight downloads by id so `download_cancel`/`download_pause`/`download_resume`
+/// can reach a running download's worker thread.
+pub struct DownloadManager {
+    next_id: AtomicU32,
+    active: Arc<Mutex<HashMap<u32, DownloadControl>>>,
+}
+
+impl DownloadManager {
+    pub fn new() -> Self {
+        DownloadManager {
+            next_id: AtomicU32::new(0),
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Streams `url` to `save_path` on a background thread, reporting progress through
+    /// `on_progress` as chunks arrive. Returns the download's id immediately.
+    pub fn start(
+        &self,
+        url: String,
+        save_path: PathBuf,
+        proxy_url: Option<String>,
+        on_progress: impl Fn(DownloadEvent) + Send + Clone + 'static,
+    ) -> u32 {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(
+                id,
+                DownloadControl {
+                    cancelled: cancelled.clone(),
+                    paused: paused.clone(),
+                },
+            );
+        }
+
+        on_progress(DownloadEvent { id, received: 0, total: None, state: "started" });
+
+        let worker_progress = on_progress.clone();
+        let worker_active = self.active.clone();
+        std::thread::spawn(move || {
+            let result = run_download(id, &url, &save_path, proxy_url.as_deref(), &cancelled, &paused, &worker_progress);
+            let (received, total, state) = match result {
+                Ok((received, total)) => (received, total, "done"),
+                Err((received, total, _)) if cancelled.load(Ordering::SeqCst) => (received, total, "cancelled"),
+                Err((received, total, _)) => (received, total, "error"),
+            };
+            if let Ok(mut active) = worker_active.lock() {
+                active.remove(&id);
+            }
+            worker_progress(DownloadEvent { id, received, total, state });
+            write_sidecar(&save_path, id, state);
+        });
+
+        id
+    }
+
+    pub fn cancel(&self, id: u32) {
+        if let Ok(active) = self.active.lock() {
+            if let Some(ctl) = active.get(&id) {
+                ctl.cancelled.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    pub fn pause(&self, id: u32) {
+        if let Ok(active) = self.active.lock() {
+            if let Some(ctl) = active.get(&id) {
+                ctl.paused.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    pub fn resume(&self, id: u32) {
+        if let Ok(active) = self.active.lock() {
+            if let Some(ctl) = active.get(&id) {
+                ctl.paused.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// Streams one download to completion. On success or failure, returns the bytes received and
+/// (if known) the total content length, so the caller can report an accurate terminal event
+/// instead of resetting progress to zero.
+fn run_download(
+    id: u32,
+    url: &str,
+    save_path: &PathBuf,
+    proxy_url: Option<&str>,
+    cancelled: &AtomicBool,
+    paused: &AtomicBool,
+    on_progress: &impl Fn(DownloadEvent),
+) -> Result<(u64, Option<u64>), (u64, Option<u64>, anyhow::Error)> {
+    let mut received: u64 = 0;
+    let mut total: Option<u64> = None;
+    match run_download_body(id, url, save_path, proxy_url, cancelled, paused, on_progress, &mut received, &mut total) {
+        Ok(()) => Ok((received, total)),
+        Err(e) => Err((received, total, e)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_download_body(
+    id: u32,
+    url: &str,
+    save_path: &PathBuf,
+    proxy_url: Option<&str>,
+    cancelled: &AtomicBool,
+    paused: &AtomicBool,
+    on_progress: &impl Fn(DownloadEvent),
+    received: &mut u64,
+    total: &mut Option<u64>,
+) -> Result<()> {
+    let mut client_builder = reqwest::blocking::Client::builder();
+    if let Some(p) = proxy_url {
+        client_builder = client_builder.proxy(reqwest::Proxy::all(p)?);
+    }
+    let client = client_builder.build()?;
+    let mut resp = client.get(url).send()?.error_for_status()?;
+    *total = resp.content_length();
+
+    let file = std::fs::File::create(save_path)
+        .with_context(|| format!("Failed to create {}", save_path.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    let mut buf = [0u8; CHUNK_SIZE];
+    let mut last_reported = Instant::now();
+
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            anyhow::bail!("download {id} cancelled");
+        }
+        while paused.load(Ordering::SeqCst) {
+            if cancelled.load(Ordering::SeqCst) {
+                anyhow::bail!("download {id} cancelled");
+            }
+            on_progress(DownloadEvent { id, received: *received, total: *total, state: "paused" });
+            std::thread::sleep(Duration::from_millis(150));
+        }
+
+        let n = resp.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        *received += n as u64;
+
+        if last_reported.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(DownloadEvent { id, received: *received, total: *total, state: "downloading" });
+            last_reported = Instant::now();
+        }
+    }
+    writer.flush()?;
+    on_progress(DownloadEvent { id, received: *received, total: *total, state: "downloading" });
+    Ok(())
+}
+
+/// Writes a small `<file>.cloak-status` sidecar JSON recording the terminal state of a download.
+fn write_sidecar(save_path: &PathBuf, id: u32, state: &str) {
+    let mut sidecar = save_path.clone().into_os_string();
+    sidecar.push(".cloak-status");
+    let body = serde_json::json!({ "id": id, "state": state }).to_string();
+    let _ = std::fs::write(sidecar, body);
+}
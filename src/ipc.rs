@@ -0,0 +1,267 @@
+use crate::downloads::DownloadManager;
+use crate::tabs::TabManager;
+use crate::{update_profile_file, validate_ipc_command, ScreenshotMode, UserEvent};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use tao::{event_loop::EventLoopProxy, window::Window};
+
+/// Everything a command handler needs to act on the browser's shared state.
+/// Cheap to clone: apart from the owned `profile_file` path, every field is a shared
+/// handle (`Rc`, `Arc` or the event-loop proxy), not owned data.
+#[derive(Clone)]
+pub struct IpcContext {
+    pub profile_file: PathBuf,
+    pub proxy_state: Arc<Mutex<Option<Value>>>,
+    pub tabs: Rc<RefCell<TabManager>>,
+    pub downloads: Arc<DownloadManager>,
+    pub window: Rc<Window>,
+    pub event_proxy: EventLoopProxy<UserEvent>,
+}
+
+/// A fully parsed, typed IPC command. `Command::parse` is the single place a raw JSON
+/// payload is pulled apart, so handlers work with concrete fields instead of each
+/// repeating `payload.get("id").and_then(...)` on their own.
+enum Command {
+    TabsSave,
+    TabsLoad,
+    ProfileSave(Value),
+    DownloadStart { url: String, file: String },
+    DownloadCancel { id: u32 },
+    DownloadPause { id: u32 },
+    DownloadResume { id: u32 },
+    ProxySet(Value),
+    TabCreate { url: String },
+    TabClose { id: u32 },
+    TabActivate { id: u32 },
+    TabNavigate { id: u32, url: String },
+    Screenshot { mode: ScreenshotMode },
+}
+
+impl Command {
+    fn parse(cmd: &str, payload: Value) -> Result<Self> {
+        match cmd {
+            "tabs_save" => Ok(Command::TabsSave),
+            "tabs_load" => Ok(Command::TabsLoad),
+            "profile_save" => Ok(Command::ProfileSave(payload)),
+            "download_start" => {
+                let url = payload
+                    .get("url")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("missing url"))?
+                    .to_string();
+                let file = payload
+                    .get("file")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("download")
+                    .to_string();
+                Ok(Command::DownloadStart { url, file })
+            }
+            "download_cancel" => Ok(Command::DownloadCancel { id: require_id(&payload)? }),
+            "download_pause" => Ok(Command::DownloadPause { id: require_id(&payload)? }),
+            "download_resume" => Ok(Command::DownloadResume { id: require_id(&payload)? }),
+            "proxy_set" => Ok(Command::ProxySet(payload)),
+            "tab_create" => Ok(Command::TabCreate {
+                url: payload
+                    .get("url")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("cloak://newtab")
+                    .to_string(),
+            }),
+            "tab_close" => Ok(Command::TabClose { id: require_id(&payload)? }),
+            "tab_activate" => Ok(Command::TabActivate { id: require_id(&payload)? }),
+            "tab_navigate" => {
+                let id = require_id(&payload)?;
+                let url = payload
+                    .get("url")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("missing url"))?
+                    .to_string();
+                Ok(Command::TabNavigate { id, url })
+            }
+            "screenshot" => Ok(Command::Screenshot { mode: ScreenshotMode::from_payload(Some(&payload)) }),
+            _ => Err(anyhow!("unknown command: {cmd}")),
+        }
+    }
+}
+
+fn require_id(payload: &Value) -> Result<u32> {
+    payload
+        .get("id")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .ok_or_else(|| anyhow!("missing id"))
+}
+
+/// Checks `cmd` against the per-command origin/shape scope in `validate_ipc_command`, parses
+/// its payload into a typed `Command`, then runs its handler. Returns an error (never panics)
+/// for disallowed, unknown, malformed, or failing commands.
+pub fn dispatch(ctx: &IpcContext, origin: &str, cmd: &str, payload: Value) -> Result<Value> {
+    if !validate_ipc_command(cmd, &payload, origin) {
+        return Err(anyhow!("command not permitted from this origin: {cmd}"));
+    }
+    match Command::parse(cmd, payload)? {
+        Command::TabsSave | Command::TabsLoad => Ok(Value::Null),
+        Command::ProfileSave(profile) => handle_profile_save(ctx, profile),
+        Command::DownloadStart { url, file } => handle_download_start(ctx, url, file),
+        Command::DownloadCancel { id } => {
+            ctx.downloads.cancel(id);
+            Ok(json!({"ok": true}))
+        }
+        Command::DownloadPause { id } => {
+            ctx.downloads.pause(id);
+            Ok(json!({"ok": true}))
+        }
+        Command::DownloadResume { id } => {
+            ctx.downloads.resume(id);
+            Ok(json!({"ok": true}))
+        }
+        Command::ProxySet(proxy) => handle_proxy_set(ctx, proxy),
+        Command::TabCreate { url } => handle_tab_create(ctx, url),
+        Command::TabClose { id } => handle_tab_close(ctx, id),
+        Command::TabActivate { id } => handle_tab_activate(ctx, id),
+        Command::TabNavigate { id, url } => handle_tab_navigate(ctx, id, url),
+        Command::Screenshot { mode } => handle_screenshot(ctx, mode),
+    }
+}
+
+fn handle_profile_save(ctx: &IpcContext, profile: Value) -> Result<Value> {
+    if let Some(parent) = ctx.profile_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&ctx.profile_file, profile.to_string())?;
+    Ok(json!({"saved": true}))
+}
+
+fn handle_download_start(ctx: &IpcContext, url: String, file: String) -> Result<Value> {
+    let save_path = rfd::FileDialog::new()
+        .set_file_name(file)
+        .save_file()
+        .ok_or_else(|| anyhow!("save dialog cancelled"))?;
+
+    let proxy_url = ctx
+        .proxy_state
+        .lock()
+        .ok()
+        .and_then(|g| g.clone())
+        .as_ref()
+        .and_then(crate::proxy_url_from_value);
+
+    let progress_proxy = ctx.event_proxy.clone();
+    let id = ctx.downloads.start(url, save_path, proxy_url, move |evt| {
+        let _ = progress_proxy.send_event(UserEvent::Download(evt));
+    });
+    Ok(json!({"id": id}))
+}
+
+fn handle_proxy_set(ctx: &IpcContext, proxy: Value) -> Result<Value> {
+    if let Ok(mut guard) = ctx.proxy_state.lock() {
+        *guard = if proxy.is_null() { None } else { Some(proxy.clone()) };
+    }
+    update_profile_file(&ctx.profile_file, |profile| {
+        if let Some(obj) = profile.as_object_mut() {
+            obj.insert("proxy".to_string(), proxy.clone());
+        }
+    });
+    Ok(json!({"proxy": proxy}))
+}
+
+fn handle_tab_create(ctx: &IpcContext, url: String) -> Result<Value> {
+    let mut tm = ctx.tabs.try_borrow_mut().map_err(|_| anyhow!("tabs are busy"))?;
+    let id = tm.create_tab(ctx.window.as_ref(), &url)?;
+    update_profile_file(&ctx.profile_file, |profile| {
+        if let Some(obj) = profile.as_object_mut() {
+            let tabs = obj.entry("tabs").or_insert_with(|| json!([]));
+            if let Some(arr) = tabs.as_array_mut() {
+                arr.push(json!({"id": id, "url": url}));
+            }
+            obj.insert("active".to_string(), json!(id));
+        }
+    });
+    Ok(json!({"id": id}))
+}
+
+fn handle_tab_close(ctx: &IpcContext, id: u32) -> Result<Value> {
+    let mut tm = ctx.tabs.try_borrow_mut().map_err(|_| anyhow!("tabs are busy"))?;
+    tm.close_tab(ctx.window.as_ref(), id)?;
+    update_profile_file(&ctx.profile_file, |profile| {
+        if let Some(tabs) = profile.get_mut("tabs").and_then(|t| t.as_array_mut()) {
+            tabs.retain(|t| t.get("id").and_then(|i| i.as_u64()) != Some(id as u64));
+        }
+        if let Some(obj) = profile.as_object_mut() {
+            obj.insert("active".to_string(), json!(tm.active_id()));
+        }
+    });
+    Ok(json!({"ok": true}))
+}
+
+fn handle_tab_activate(ctx: &IpcContext, id: u32) -> Result<Value> {
+    let mut tm = ctx.tabs.try_borrow_mut().map_err(|_| anyhow!("tabs are busy"))?;
+    tm.activate(ctx.window.as_ref(), id)?;
+    update_profile_file(&ctx.profile_file, |profile| {
+        if let Some(obj) = profile.as_object_mut() {
+            obj.insert("active".to_string(), json!(id));
+        }
+    });
+    Ok(json!({"ok": true}))
+}
+
+fn handle_tab_navigate(ctx: &IpcContext, id: u32, url: String) -> Result<Value> {
+    let tm = ctx.tabs.try_borrow().map_err(|_| anyhow!("tabs are busy"))?;
+    tm.navigate(id, &url)?;
+    Ok(json!({"ok": true}))
+}
+
+fn handle_screenshot(ctx: &IpcContext, mode: ScreenshotMode) -> Result<Value> {
+    let save_path = rfd::FileDialog::new()
+        .set_file_name("screenshot.png")
+        .save_file()
+        .ok_or_else(|| anyhow!("save dialog cancelled"))?;
+    ctx.event_proxy
+        .send_event(UserEvent::Screenshot { save_path, mode })
+        .map_err(|_| anyhow!("event loop is gone"))?;
+    Ok(json!({"requested": true}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_well_formed_payloads() {
+        assert!(Command::parse("tabs_save", Value::Null).is_ok());
+        assert!(Command::parse("tabs_load", Value::Null).is_ok());
+        assert!(matches!(
+            Command::parse("download_start", json!({"url": "https://example.com"})).unwrap(),
+            Command::DownloadStart { url, file } if url == "https://example.com" && file == "download"
+        ));
+        assert!(matches!(
+            Command::parse("download_start", json!({"url": "https://example.com", "file": "a.bin"})).unwrap(),
+            Command::DownloadStart { file, .. } if file == "a.bin"
+        ));
+        assert!(matches!(
+            Command::parse("tab_close", json!({"id": 3})).unwrap(),
+            Command::TabClose { id: 3 }
+        ));
+        assert!(matches!(
+            Command::parse("tab_create", Value::Null).unwrap(),
+            Command::TabCreate { url } if url == "cloak://newtab"
+        ));
+        assert!(matches!(
+            Command::parse("tab_navigate", json!({"id": 1, "url": "cloak://settings"})).unwrap(),
+            Command::TabNavigate { id: 1, url } if url == "cloak://settings"
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_payloads() {
+        assert!(Command::parse("download_start", json!({})).is_err());
+        assert!(Command::parse("download_cancel", json!({})).is_err());
+        assert!(Command::parse("tab_close", json!({"id": "not-a-number"})).is_err());
+        assert!(Command::parse("tab_navigate", json!({"id": 1})).is_err());
+        assert!(Command::parse("not_a_real_command", Value::Null).is_err());
+    }
+}
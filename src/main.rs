@@ -5,16 +5,131 @@ use tao::{
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
-use wry::{WebContext, WebViewBuilder};
+use wry::{ProxyConfig, ProxyEndpoint, WebContext, WebViewBuilder};
+use image::ImageEncoder;
 use serde_json::json;
 use directories::ProjectDirs;
 use std::path::PathBuf;
 use std::fs;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+mod downloads;
+mod ipc;
+mod tabs;
+use downloads::{DownloadEvent, DownloadManager};
+use tabs::TabManager;
+
+/// Events sent from background worker threads (or the IPC handler, for work that must
+/// run on the UI thread) back into the `tao` event loop, where it's safe to touch the webview.
+pub(crate) enum UserEvent {
+    Download(DownloadEvent),
+    Screenshot { save_path: PathBuf, mode: ScreenshotMode },
+}
+
+/// Which region of the page to capture for the `screenshot` IPC command.
+#[derive(Clone, Copy)]
+pub(crate) enum ScreenshotMode {
+    FullPage,
+    Viewport,
+}
+
+impl ScreenshotMode {
+    pub(crate) fn from_payload(payload: Option<&serde_json::Value>) -> Self {
+        match payload.and_then(|p| p.get("mode")).and_then(|m| m.as_str()) {
+            Some("full") | Some("full_page") => ScreenshotMode::FullPage,
+            _ => ScreenshotMode::Viewport,
+        }
+    }
+}
+
+/// Captures the webview's rendered surface and encodes it as PNG bytes.
+fn capture_webview_png(webview: &wry::WebView, mode: ScreenshotMode) -> Result<Vec<u8>> {
+    let region = match mode {
+        ScreenshotMode::FullPage => wry::CaptureRegion::FullPage,
+        ScreenshotMode::Viewport => wry::CaptureRegion::Viewport,
+    };
+    let image = webview.capture(region).context("Failed to capture webview surface")?;
+    let mut png_bytes = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png_bytes)
+        .write_image(&image.rgba, image.width, image.height, image::ColorType::Rgba8)
+        .context("Failed to encode screenshot as PNG")?;
+    Ok(png_bytes)
+}
+
+/// Origins the IPC handler will accept privileged commands from.
+/// Everything else (any remote http/https page loaded in the webview, and transient
+/// states like `about:blank` that a webview can briefly report mid-navigation) is
+/// untrusted -- `cloak://` is the only origin the bootstrap ever intentionally sits on.
+fn is_trusted_origin(origin: &str) -> bool {
+    origin.starts_with("cloak://")
+}
 
 // Include the JavaScript UI code from external file
 const UI_JS: &str = include_str!("ui.js");
 
-fn create_window(event_loop: &EventLoop<()>) -> Result<tao::window::Window> {
+/// The scheme served by our custom protocol handler. Pages loaded under this
+/// scheme share a single, stable, privileged origin that the IPC allowlist trusts.
+/// Registered on every webview that may load `cloak://` pages (chrome + tabs),
+/// since wry custom protocols are per-webview, not process-global.
+pub(crate) const CLOAK_SCHEME: &str = "cloak";
+
+/// Height (in logical pixels) of the chrome strip (tab bar/address bar) reserved
+/// at the top of the window. Tab webviews are positioned below it so the chrome
+/// stays reachable instead of being covered by the active tab's content.
+pub(crate) const CHROME_HEIGHT: f64 = 72.0;
+
+fn newtab_html() -> String {
+    r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>New Tab</title></head>
+<body>
+<div id="app"></div>
+<script src="cloak://ui.js"></script>
+</body>
+</html>"#.to_string()
+}
+
+fn settings_html() -> String {
+    r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>Settings</title></head>
+<body>
+<div id="app" data-page="settings"></div>
+<script src="cloak://ui.js"></script>
+</body>
+</html>"#.to_string()
+}
+
+fn error_html(message: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>Error</title></head>
+<body>
+<h1>Something went wrong</h1>
+<p>{}</p>
+</body>
+</html>"#,
+        message
+    )
+}
+
+/// Resolves a `cloak://` request to a (mime type, body) pair. The host component
+/// is the "page" (`cloak://newtab`, `cloak://settings`), matching how wry routes
+/// custom-protocol requests.
+pub(crate) fn cloak_protocol_response(request: &wry::http::Request<Vec<u8>>) -> (&'static str, Vec<u8>) {
+    let page = request.uri().host().unwrap_or("");
+    match page {
+        "ui.js" => ("text/javascript", UI_JS.as_bytes().to_vec()),
+        "newtab" | "" => ("text/html", newtab_html().into_bytes()),
+        "settings" => ("text/html", settings_html().into_bytes()),
+        other => ("text/html", error_html(&format!("No such page: cloak://{other}")).into_bytes()),
+    }
+}
+
+fn create_window(event_loop: &EventLoop<UserEvent>) -> Result<tao::window::Window> {
     // Try to size the window to the user's primary monitor resolution
     let (width, height) = if let Some(monitor) = event_loop.primary_monitor() {
         let scale = monitor.scale_factor();
@@ -37,6 +152,16 @@ fn create_window(event_loop: &EventLoop<()>) -> Result<tao::window::Window> {
     Ok(window)
 }
 
+/// The chrome webview occupies a fixed-height strip at the top of the window;
+/// tab webviews (see `tabs::tab_bounds`) fill the remainder below it.
+fn chrome_bounds(window: &tao::window::Window) -> wry::Rect {
+    let size: LogicalSize<f64> = window.inner_size().to_logical(window.scale_factor());
+    wry::Rect {
+        position: tao::dpi::Position::Logical(LogicalPosition::new(0.0, 0.0)),
+        size: tao::dpi::Size::Logical(LogicalSize::new(size.width, CHROME_HEIGHT)),
+    }
+}
+
 fn get_data_directory() -> Result<PathBuf> {
     let proj_dirs = ProjectDirs::from("com", "cloak", "browser")
         .context("Failed to get project directories")?;
@@ -47,22 +172,23 @@ fn get_data_directory() -> Result<PathBuf> {
     Ok(data_dir.to_path_buf())
 }
 
-fn create_bootstrap_script(profile_file: &PathBuf, start_url: &str) -> Result<String> {
-    // Build default profile
+fn load_profile(profile_file: &PathBuf, start_url: &str) -> serde_json::Value {
     let default_profile = serde_json::json!({
         "tabs": [{"url": start_url, "title": "New Tab"}],
         "active": 0,
         "bookmarks": [],
-        "history": []
+        "history": [],
+        "proxy": null
     });
 
-    // Load profile from disk if present
-    let profile_value = match std::fs::read_to_string(profile_file) {
+    match std::fs::read_to_string(profile_file) {
         Ok(s) => serde_json::from_str::<serde_json::Value>(&s).unwrap_or(default_profile),
         Err(_) => default_profile,
-    };
+    }
+}
 
-    let profile_literal = serde_json::to_string(&profile_value).unwrap_or("{}".into());
+fn create_bootstrap_script(profile_value: &serde_json::Value) -> Result<String> {
+    let profile_literal = serde_json::to_string(profile_value).unwrap_or("{}".into());
     let script = format!(
         r#"
         // Bootstrap for cloak_browser
@@ -75,26 +201,86 @@ fn create_bootstrap_script(profile_file: &PathBuf, start_url: &str) -> Result<St
     Ok(script)
 }
 
-fn validate_ipc_command(cmd: &str, _data: &serde_json::Value) -> bool {
+/// Builds the wry proxy config to route all webview traffic through, from the
+/// profile's `proxy` field: `{"scheme": "socks5"|"http", "host": "...", "port": 1080}`.
+fn proxy_config_from_profile(profile_value: &serde_json::Value) -> Option<ProxyConfig> {
+    let proxy = profile_value.get("proxy")?;
+    if proxy.is_null() {
+        return None;
+    }
+    let host = proxy.get("host").and_then(|v| v.as_str())?.to_string();
+    let port = proxy.get("port").and_then(|v| v.as_u64())?.to_string();
+    let endpoint = ProxyEndpoint { host, port };
+    match proxy.get("scheme").and_then(|v| v.as_str()) {
+        Some("http") => Some(ProxyConfig::Http(endpoint)),
+        _ => Some(ProxyConfig::Socks5(endpoint)),
+    }
+}
+
+/// Builds a `scheme://host:port` proxy URL for `reqwest::Proxy::all`, from the
+/// same `proxy` shape stored in the profile and sent by the `proxy_set` IPC command.
+pub(crate) fn proxy_url_from_value(proxy: &serde_json::Value) -> Option<String> {
+    if proxy.is_null() {
+        return None;
+    }
+    let scheme = proxy.get("scheme").and_then(|v| v.as_str()).unwrap_or("socks5");
+    let host = proxy.get("host").and_then(|v| v.as_str())?;
+    let port = proxy.get("port").and_then(|v| v.as_u64())?;
+    Some(format!("{scheme}://{host}:{port}"))
+}
+
+/// Per-command scope check: is `cmd` allowed from `origin` at all, and does `data` look like
+/// a well-formed payload for it? This runs before `ipc::Command::parse`, so a malformed or
+/// out-of-scope payload is rejected here rather than surfacing as a per-handler parse error.
+pub(crate) fn validate_ipc_command(cmd: &str, data: &serde_json::Value, origin: &str) -> bool {
+    if !is_trusted_origin(origin) {
+        return false;
+    }
     match cmd {
-        "tabs_save" | "tabs_load" => true,
+        "tabs_save" | "tabs_load" | "tab_create" | "screenshot" => true,
+        "profile_save" | "proxy_set" => data.is_object() || data.is_null(),
+        "download_start" => data.get("url").and_then(|v| v.as_str()).is_some(),
+        "download_cancel" | "download_pause" | "download_resume" | "tab_close" | "tab_activate" => {
+            data.get("id").and_then(|v| v.as_u64()).is_some()
+        }
+        "tab_navigate" => {
+            data.get("id").and_then(|v| v.as_u64()).is_some()
+                && data.get("url").and_then(|v| v.as_str()).is_some()
+        }
         _ => false,
     }
 }
 
+/// Reads `profile.json` (or an empty object if missing/invalid), applies `mutate`, and writes it back.
+pub(crate) fn update_profile_file(profile_file: &PathBuf, mutate: impl FnOnce(&mut serde_json::Value)) {
+    let mut profile_on_disk = std::fs::read_to_string(profile_file)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .unwrap_or_else(|| json!({}));
+    mutate(&mut profile_on_disk);
+    if let Some(parent) = profile_file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(profile_file, profile_on_disk.to_string());
+}
+
 fn main() -> Result<()> {
     // Get data directory for storing tabs and settings
     let data_dir = get_data_directory()?;
     let profile_file = data_dir.join("profile.json");
     
-    // Create bootstrap script
-    let start_url = "about:blank";
-    let bootstrap_script = create_bootstrap_script(&profile_file, start_url)?;
-    
-    // Create event loop and window
-    let event_loop = EventLoop::new();
-    let window = create_window(&event_loop)?;
-    
+    // Load the persisted profile once and derive the bootstrap script and proxy config from it
+    let start_url = "cloak://newtab";
+    let profile_value = load_profile(&profile_file, start_url);
+    let bootstrap_script = create_bootstrap_script(&profile_value)?;
+    let proxy_config = proxy_config_from_profile(&profile_value);
+
+    // Create event loop and window. A custom user event lets download worker threads
+    // hand progress back to the loop, where it's safe to call `evaluate_script`.
+    let event_loop: EventLoop<UserEvent> = EventLoop::with_user_event();
+    let event_proxy = event_loop.create_proxy();
+    let window = Rc::new(create_window(&event_loop)?);
+
     // Create web context - this is required for the webview to function
     // Ensure a stable WebView2 user data folder under LOCALAPPDATA so cookies/sessions persist
     let proj_dirs_local = ProjectDirs::from("com", "cloak", "browser")
@@ -103,65 +289,213 @@ fn main() -> Result<()> {
     let _ = std::fs::create_dir_all(&wv2_dir);
     std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", &wv2_dir);
     let mut web_context = WebContext::new(Some(data_dir.clone()));
-    
-    // Build and create the webview
-    let _webview = WebViewBuilder::new(&window)
+
+    // Tracks the webview's current top-level origin so the IPC handler can reject
+    // commands from anything other than our trusted internal pages.
+    let current_origin = Arc::new(Mutex::new(start_url.to_string()));
+    let nav_origin = current_origin.clone();
+    let ipc_origin = current_origin.clone();
+
+    // Current proxy settings, seeded from the profile and updatable at runtime via `proxy_set`.
+    // Downloads pick this up immediately; the webview's own proxy only takes effect on restart.
+    let proxy_state = Arc::new(Mutex::new(profile_value.get("proxy").cloned().filter(|v| !v.is_null())));
+
+    // Each browsing tab gets its own isolated webview, managed independently of the chrome below.
+    // Tabs load real, untrusted web content, so they must honor the same proxy as everything
+    // else in a privacy browser -- pass it through instead of only applying it to the chrome.
+    let tab_manager = Rc::new(RefCell::new(TabManager::new(&data_dir, proxy_config.clone())));
+    let saved_tabs = profile_value.get("tabs").and_then(|t| t.as_array()).cloned().unwrap_or_default();
+    let saved_active = profile_value.get("active").and_then(|a| a.as_u64()).unwrap_or(0) as usize;
+    let mut seeded_tabs = Vec::new();
+    {
+        let mut tm = tab_manager.borrow_mut();
+        for entry in &saved_tabs {
+            let url = entry.get("url").and_then(|u| u.as_str()).unwrap_or(start_url);
+            if let Ok(id) = tm.create_tab(window.as_ref(), url) {
+                seeded_tabs.push(json!({"id": id, "url": url}));
+            }
+        }
+        if let Some(active_entry) = seeded_tabs.get(saved_active).or_else(|| seeded_tabs.first()) {
+            if let Some(id) = active_entry.get("id").and_then(|i| i.as_u64()) {
+                let _ = tm.activate(window.as_ref(), id as u32);
+            }
+        }
+    }
+    update_profile_file(&profile_file, |profile| {
+        if let Some(obj) = profile.as_object_mut() {
+            obj.insert("tabs".to_string(), json!(seeded_tabs));
+            if let Some(id) = tab_manager.borrow().active_id() {
+                obj.insert("active".to_string(), json!(id));
+            }
+        }
+    });
+    let tabs_resize = tab_manager.clone();
+    let window_resize = window.clone();
+    let tabs_screenshot = tab_manager.clone();
+
+    // Tracks in-flight downloads so download_cancel/pause/resume can reach a running one.
+    let download_manager = Arc::new(DownloadManager::new());
+
+    // Bundles every handle a command handler needs to act on shared state.
+    let ipc_ctx = ipc::IpcContext {
+        profile_file: profile_file.clone(),
+        proxy_state: proxy_state.clone(),
+        tabs: tab_manager.clone(),
+        downloads: download_manager.clone(),
+        window: window.clone(),
+        event_proxy: event_proxy.clone(),
+    };
+
+    // Holds the chrome webview once built, so the IPC handler (which runs before `.build()`
+    // returns) can resolve commands back into the page via `evaluate_script`.
+    let webview_handle: Rc<RefCell<Option<wry::WebView>>> = Rc::new(RefCell::new(None));
+    let webview_handle_ipc = webview_handle.clone();
+    let webview_handle_events = webview_handle.clone();
+    let webview_handle_resize = webview_handle.clone();
+
+    // Build and create the webview. It's pinned to a fixed-height strip at the top of the
+    // window (see `chrome_bounds`) so the tab webviews stacked below it stay reachable.
+    let mut webview_builder = WebViewBuilder::new(window.as_ref())
         .with_url(start_url)
         .with_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0")
         .with_devtools(true)
         .with_initialization_script(&bootstrap_script)
-        .with_initialization_script(UI_JS)
+        .with_bounds(chrome_bounds(window.as_ref()))
         .with_web_context(&mut web_context)
-        .with_ipc_handler(move |payload| {
-            // Expect JSON string payload with { cmd, payload }
-            let parsed: Result<serde_json::Value, _> = serde_json::from_str(payload.body());
-            if let Ok(v) = parsed {
-                if let Some(cmd) = v.get("cmd").and_then(|c| c.as_str()) {
-                    match cmd {
-                        "profile_save" => {
-                            if let Some(profile) = v.get("payload") {
-                                if let Some(parent) = profile_file.parent() { let _ = std::fs::create_dir_all(parent); }
-                                let _ = std::fs::write(&profile_file, profile.to_string());
-                            }
-                        }
-                        "download_start" => {
-                            if let Some(p) = v.get("payload").cloned() {
-                                let url = p.get("url").and_then(|u| u.as_str()).unwrap_or("").to_string();
-                                let file = p.get("file").and_then(|u| u.as_str()).unwrap_or("download").to_string();
-                                 // Choose a save location with a native dialog
-                                 let save_path = match rfd::FileDialog::new().set_file_name(file).save_file() {
-                                     Some(path) => path,
-                                     None => return,
-                                 };
-                                 // Kick off a blocking download on a new thread
-                                std::thread::spawn(move || {
-                                     let res = (|| -> anyhow::Result<()> {
-                                        let resp = reqwest::blocking::get(&url)?;
-                                         let mut file = std::fs::File::create(&save_path)?;
-                                         let mut src = std::io::Cursor::new(resp.bytes()?);
-                                         std::io::copy(&mut src, &mut file)?;
-                                         Ok(())
-                                     })();
-                                     // Optionally: write a small sidecar json with status
-                                     let _ = res;
-                                 });
-                             }
-                         }
-                        _ => {}
-                    }
-                }
+        .with_custom_protocol(CLOAK_SCHEME.into(), move |request| {
+            let (mime, body) = cloak_protocol_response(&request);
+            wry::http::Response::builder()
+                .header("Content-Type", mime)
+                .body(body)
+                .map_err(Into::into)
+        })
+        .with_navigation_handler(move |url| {
+            if let Ok(mut guard) = nav_origin.lock() {
+                *guard = url.clone();
             }
+            true
         })
+        .with_ipc_handler(move |payload| {
+            // Expect JSON string payload with { id, cmd, payload }; `id` round-trips back
+            // to `window.__CB_IPC_RESOLVE__` so the JS side can await the matching promise.
+            let Ok(v) = serde_json::from_str::<serde_json::Value>(payload.body()) else { return };
+            let Some(cmd) = v.get("cmd").and_then(|c| c.as_str()).map(|s| s.to_string()) else { return };
+            let request_id = v.get("id").cloned().unwrap_or(serde_json::Value::Null);
+            let data = v.get("payload").cloned().unwrap_or(serde_json::Value::Null);
+            let origin = ipc_origin.lock().map(|g| g.clone()).unwrap_or_default();
+
+            let result = ipc::dispatch(&ipc_ctx, &origin, &cmd, data);
+            let (ok, value) = match result {
+                Ok(value) => (true, value),
+                Err(err) => (false, json!(err.to_string())),
+            };
+            let resolve_script = format!(
+                "window.__CB_IPC_RESOLVE__ && window.__CB_IPC_RESOLVE__({}, {}, {});",
+                serde_json::to_string(&request_id).unwrap_or_else(|_| "null".into()),
+                ok,
+                serde_json::to_string(&value).unwrap_or_else(|_| "null".into()),
+            );
+            if let Some(wv) = webview_handle_ipc.borrow().as_ref() {
+                let _ = wv.evaluate_script(&resolve_script);
+            }
+        });
+    if let Some(cfg) = proxy_config {
+        webview_builder = webview_builder.with_proxy_config(cfg);
+    }
+    let webview = webview_builder
         .build()
         .context("Failed to create webview")?;
-    
+    *webview_handle.borrow_mut() = Some(webview);
+
     // Start the event loop
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
         match event {
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => *control_flow = ControlFlow::Exit,
+            Event::WindowEvent { event: WindowEvent::Resized(_), .. } => {
+                let _ = tabs_resize.borrow().resize(window_resize.as_ref());
+                if let Some(wv) = webview_handle_resize.borrow().as_ref() {
+                    let _ = wv.set_bounds(chrome_bounds(window_resize.as_ref()));
+                }
+            }
+            Event::UserEvent(UserEvent::Download(evt)) => {
+                if let Some(wv) = webview_handle_events.borrow().as_ref() {
+                    let _ = wv.evaluate_script(&evt.to_js());
+                }
+            }
+            Event::UserEvent(UserEvent::Screenshot { save_path, mode }) => {
+                // Capture the tab the user is actually looking at, not the chrome webview
+                // underneath it -- that's what "screenshot" means to the user.
+                let tabs = tabs_screenshot.borrow();
+                let Some(wv) = tabs.active_webview() else { return };
+                let callback = match capture_webview_png(wv, mode) {
+                    Ok(png) if std::fs::write(&save_path, png).is_ok() => {
+                        format!(
+                            "window.__CB_SCREENSHOT__ && window.__CB_SCREENSHOT__({{path:{:?}}});",
+                            save_path.display().to_string()
+                        )
+                    }
+                    _ => "window.__CB_SCREENSHOT__ && window.__CB_SCREENSHOT__({error:true});".to_string(),
+                };
+                // Resolve back into the privileged chrome UI (the only webview with an ipc
+                // resolve-listener), not the tab's own page -- that page never defines
+                // __CB_SCREENSHOT__, and handing it the saved file's path would leak it to
+                // whatever untrusted site the tab happens to be showing.
+                if let Some(chrome_wv) = webview_handle_events.borrow().as_ref() {
+                    let _ = chrome_wv.evaluate_script(&callback);
+                }
+            }
             _ => {}
         }
     });
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn about_blank_is_not_trusted() {
+        // A webview can briefly report `about:blank` mid-navigation; it must never be
+        // treated as the privileged origin, or a remote page could race it for IPC access.
+        assert!(!is_trusted_origin("about:blank"));
+    }
+
+    #[test]
+    fn only_cloak_scheme_is_trusted() {
+        assert!(is_trusted_origin("cloak://newtab"));
+        assert!(is_trusted_origin("cloak://settings"));
+        assert!(!is_trusted_origin("https://example.com"));
+        assert!(!is_trusted_origin("http://localhost"));
+    }
+
+    #[test]
+    fn validate_ipc_command_rejects_untrusted_origins() {
+        let payload = json!({"url": "https://example.com"});
+        assert!(!validate_ipc_command("download_start", &payload, "https://evil.example"));
+        assert!(!validate_ipc_command("profile_save", &json!({}), "about:blank"));
+        assert!(validate_ipc_command("download_start", &payload, "cloak://newtab"));
+    }
+
+    #[test]
+    fn validate_ipc_command_checks_per_command_shape() {
+        let origin = "cloak://newtab";
+        // download_start needs a url.
+        assert!(!validate_ipc_command("download_start", &json!({}), origin));
+        assert!(validate_ipc_command("download_start", &json!({"url": "https://example.com"}), origin));
+        // download_cancel/tab_close/tab_activate need a numeric id.
+        assert!(!validate_ipc_command("tab_close", &json!({}), origin));
+        assert!(!validate_ipc_command("tab_close", &json!({"id": "3"}), origin));
+        assert!(validate_ipc_command("tab_close", &json!({"id": 3}), origin));
+        // tab_navigate needs both an id and a url.
+        assert!(!validate_ipc_command("tab_navigate", &json!({"id": 1}), origin));
+        assert!(!validate_ipc_command("tab_navigate", &json!({"url": "cloak://settings"}), origin));
+        assert!(validate_ipc_command("tab_navigate", &json!({"id": 1, "url": "cloak://settings"}), origin));
+        // tabs_save/tabs_load/tab_create/screenshot are shape-free -- any payload is fine.
+        assert!(validate_ipc_command("tab_create", &serde_json::Value::Null, origin));
+        assert!(validate_ipc_command("screenshot", &json!({"mode": "viewport"}), origin));
+        // Unknown commands are always rejected.
+        assert!(!validate_ipc_command("not_a_real_command", &json!({}), origin));
+    }
+}
+
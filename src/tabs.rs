@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tao::{dpi::LogicalSize, window::Window};
+use wry::{ProxyConfig, WebContext, WebView, WebViewBuilder};
+
+/// A single browsing tab: its own `WebView` backed by its own `WebContext`
+/// (and therefore its own cookie jar / storage / user-data folder), so tabs
+/// can't see each other's cookies or storage.
+struct Tab {
+    id: u32,
+    webview: WebView,
+    // Kept alive for as long as the tab exists; dropping it tears down the tab's storage handle.
+    _context: WebContext,
+}
+
+/// Owns every content tab's native `WebView`, stacked on top of one another and
+/// shown/hidden by resizing the inactive ones to nothing. Only one tab is visible
+/// (and interactive) at a time, matching the single-window/multi-webview model.
+pub struct TabManager {
+    tabs_dir: PathBuf,
+    tabs: Vec<Tab>,
+    active: Option<u32>,
+    next_id: u32,
+    // Applied to every tab's webview so real page loads honor the configured proxy the
+    // same way the chrome webview and the download manager do.
+    proxy_config: Option<ProxyConfig>,
+}
+
+impl TabManager {
+    pub fn new(data_dir: &PathBuf, proxy_config: Option<ProxyConfig>) -> Self {
+        TabManager {
+            tabs_dir: data_dir.join("tabs"),
+            tabs: Vec::new(),
+            active: None,
+            next_id: 0,
+            proxy_config,
+        }
+    }
+
+    /// Creates a new tab's webview under its own per-tab user-data folder and activates it.
+    pub fn create_tab(&mut self, window: &Window, url: &str) -> Result<u32> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let tab_dir = self.tabs_dir.join(id.to_string());
+        std::fs::create_dir_all(&tab_dir)
+            .with_context(|| format!("Failed to create tab data dir for tab {id}"))?;
+        let mut context = WebContext::new(Some(tab_dir));
+
+        let mut builder = WebViewBuilder::new(window)
+            .with_url(url)
+            .with_bounds(tab_bounds(window, false))
+            .with_web_context(&mut context)
+            // Tabs can load `cloak://newtab`/`cloak://settings` just like the chrome webview;
+            // wry registers custom protocols per-webview, so every tab needs its own handler.
+            .with_custom_protocol(crate::CLOAK_SCHEME.into(), |request| {
+                let (mime, body) = crate::cloak_protocol_response(&request);
+                wry::http::Response::builder()
+                    .header("Content-Type", mime)
+                    .body(body)
+                    .map_err(Into::into)
+            });
+        if let Some(cfg) = self.proxy_config.clone() {
+            builder = builder.with_proxy_config(cfg);
+        }
+        let webview = builder
+            .build()
+            .with_context(|| format!("Failed to create webview for tab {id}"))?;
+
+        self.tabs.push(Tab { id, webview, _context: context });
+        self.activate(window, id)?;
+        Ok(id)
+    }
+
+    /// Hides and drops the tab's webview (and with it, its isolated storage), then
+    /// activates a neighbour if the closed tab was the active one.
+    pub fn close_tab(&mut self, window: &Window, id: u32) -> Result<()> {
+        let Some(idx) = self.tabs.iter().position(|t| t.id == id) else {
+            return Ok(());
+        };
+        self.tabs.remove(idx);
+
+        if self.active == Some(id) {
+            self.active = None;
+            if let Some(next) = self.tabs.get(idx).or_else(|| self.tabs.last()).map(|t| t.id) {
+                self.activate(window, next)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows the given tab's webview at full size and hides every other tab, resizing
+    /// both so the newly-active tab isn't left at the zero-size bounds it was hidden at.
+    pub fn activate(&mut self, window: &Window, id: u32) -> Result<()> {
+        if !self.tabs.iter().any(|t| t.id == id) {
+            return Ok(());
+        }
+        for tab in &self.tabs {
+            let visible = tab.id == id;
+            tab.webview.set_bounds(tab_bounds(window, visible))?;
+            tab.webview.set_visible(visible)?;
+        }
+        self.active = Some(id);
+        Ok(())
+    }
+
+    pub fn navigate(&self, id: u32, url: &str) -> Result<()> {
+        if let Some(tab) = self.tabs.iter().find(|t| t.id == id) {
+            tab.webview.load_url(url)?;
+        }
+        Ok(())
+    }
+
+    /// Resizes every tab's webview to match the window, keeping only the active one full-size.
+    pub fn resize(&self, window: &Window) -> Result<()> {
+        for tab in &self.tabs {
+            tab.webview.set_bounds(tab_bounds(window, self.active == Some(tab.id)))?;
+        }
+        Ok(())
+    }
+
+    pub fn active_id(&self) -> Option<u32> {
+        self.active
+    }
+
+    /// The active tab's webview, e.g. for screenshotting what the user is actually looking at
+    /// rather than the chrome webview underneath it.
+    pub fn active_webview(&self) -> Option<&WebView> {
+        let id = self.active?;
+        self.tabs.iter().find(|t| t.id == id).map(|t| &t.webview)
+    }
+}
+
+/// Visible tabs fill the window below a fixed-height chrome strip; hidden tabs collapse to nothing.
+fn tab_bounds(window: &Window, visible: bool) -> wry::Rect {
+    const CHROME_HEIGHT: f64 = crate::CHROME_HEIGHT;
+    let size = window.inner_size();
+    let size: LogicalSize<f64> = size.to_logical(window.scale_factor());
+    wry::Rect {
+        position: tao::dpi::Position::Logical(tao::dpi::LogicalPosition::new(0.0, CHROME_HEIGHT)),
+        size: tao::dpi::Size::Logical(if visible {
+            LogicalSize::new(size.width, (size.height - CHROME_HEIGHT).max(0.0))
+        } else {
+            LogicalSize::new(0.0, 0.0)
+        }),
+    }
+}